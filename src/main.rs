@@ -1,12 +1,18 @@
 use crate::renderer::Renderer;
+use crate::scene::Hittable;
 
 pub mod renderer;
+pub mod scene;
 
 extern crate glm;
+extern crate rand;
+
+use std::collections::VecDeque;
+use std::sync::{mpsc, Mutex};
 
 use glm::{ivec2, IVec2, Vector2, Vector3};
 use glm::{cross, dot, normalize};
-use renderer::{color, Color};
+use renderer::{color, Color, GpuCameraParams};
 
 pub type Vec2 = Vector2<f64>;
 pub type Vec3 = Vector3<f64>;
@@ -19,12 +25,23 @@ fn vec3(x: f64, y: f64, z: f64) -> Vec3 {
     Vec3{x, y, z}
 }
 
+// component-wise multiply, e.g. for tinting a traced color by a material's albedo
+fn mul(a: Vec3, b: Vec3) -> Vec3 {
+    vec3(a.x * b.x, a.y * b.y, a.z * b.z)
+}
+
 
 pub const ASPECT_RATIO : f64 = 36.0 / 24.0;
 
+// how many bounces a ray is allowed before we give up and call it black
+pub const MAX_DEPTH : u32 = 8;
+
 pub const WINDOW_HEIGHT : usize = 512;
 pub const WINDOW_WIDTH : usize = (WINDOW_HEIGHT as f64 * ASPECT_RATIO) as usize;
 
+// side length, in pixels, of a unit of work handed out to a render thread
+pub const BLOCK_SIZE : usize = 16;
+
 // In this project, 1 unit of space = 1 meter
 
 // return an engine space representation of n centimeters;
@@ -38,15 +55,65 @@ fn millimeters(n : f64) -> f64 {
 }
 
 
+#[derive(Copy, Clone)]
+enum Material {
+    Lambertian { albedo : Vec3 },
+    Metal { albedo : Vec3, fuzz : f64 },
+    Dielectric { ior : f64 },
+}
+
+impl Material {
+    // How much light passing through a surface of this material survives, per shadow
+    // ray crossing. Opaque materials block light entirely; glass lets most of it
+    // through, which is what gives dielectrics their tinted rather than pitch-black shadows.
+    fn transmission_coefficient(&self) -> Vec3 {
+        match self {
+            Material::Dielectric { .. } => vec3(0.9, 0.9, 0.9),
+            Material::Lambertian { .. } | Material::Metal { .. } => vec3(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// A point light source for the direct-lighting shadow-ray pass
+struct PointLight {
+    position : Vec3,
+    color : Vec3,
+    intensity : f64,
+}
+
+// A sphere's linear motion across the camera's shutter interval: it sits at `center0`
+// (the Sphere's `center` field) at `time0` and `center1` at `time1`.
+#[derive(Copy, Clone)]
+struct Motion {
+    center1 : Vec3,
+    time0 : f64,
+    time1 : f64,
+}
+
 struct Sphere {
     center : Vec3,
     radius : f64,
+    material : Material,
+    motion : Option<Motion>,
+}
+
+impl Sphere {
+    // The sphere's center at a given ray time; stationary unless `motion` is set.
+    fn center_at(&self, time : f64) -> Vec3 {
+        match self.motion {
+            Some(Motion { center1, time0, time1 }) => {
+                self.center + (center1 - self.center) * ((time - time0) / (time1 - time0))
+            }
+            None => self.center,
+        }
+    }
 }
 
 #[derive(Debug)]
 struct Ray {
     origin : Vec3,
     dir : Vec3,
+    time : f64,
 }
 
 #[derive(Copy, Clone)]
@@ -54,46 +121,200 @@ struct HitRecord {
     dist: f64,
     norm: Vec3,
     point: Vec3,
-    //mat: Material
+    mat: Material
 }
 
 
-impl Ray {
-    fn new(origin : Vec3, dir : Vec3) -> Self {
-        Self {origin, dir}
+fn random_f64() -> f64 {
+    rand::random::<f64>()
+}
+
+fn random_f64_range(min : f64, max : f64) -> f64 {
+    min + (max - min) * random_f64()
+}
+
+fn random_in_unit_sphere() -> Vec3 {
+    loop {
+        let p = vec3(random_f64_range(-1.0, 1.0), random_f64_range(-1.0, 1.0), random_f64_range(-1.0, 1.0));
+        if dot(p, p) < 1.0 {
+            return p;
+        }
     }
+}
+
+fn random_unit_vector() -> Vec3 {
+    normalize(random_in_unit_sphere())
+}
 
-    fn cast(&self) -> Vec3 {
-        let sphere = Sphere {center : vec3(0.0, 0.0, 0.0), radius: 2.0};
-        let sphere2 = Sphere {center : vec3(0.0, 3.0, 0.0), radius: 1.0};
-    
-        let sphere_hit = ray_sphere_intersection(self, &sphere, 0.0, 10_000.0);
-        let sphere2_hit = ray_sphere_intersection(self, &sphere2, 0.0, 10_000.0);
-
-        if let Some(hit) = sphere_hit {
-            if let Some(hit2) = sphere2_hit {
-                return match hit.dist < hit2.dist {
-                    true => vec3(1.0, 0.0, 0.0),
-                    false => vec3(0.0, 1.0, 0.0)
-                };
+fn random_in_unit_disk() -> Vec2 {
+    loop {
+        let p = vec2(random_f64_range(-1.0, 1.0), random_f64_range(-1.0, 1.0));
+        if p.x * p.x + p.y * p.y < 1.0 {
+            return p;
+        }
+    }
+}
+
+fn near_zero(v : Vec3) -> bool {
+    const EPS : f64 = 1e-8;
+    v.x.abs() < EPS && v.y.abs() < EPS && v.z.abs() < EPS
+}
+
+fn reflect(d : Vec3, n : Vec3) -> Vec3 {
+    d - n * (2.0 * dot(d, n))
+}
+
+// n and ni_over_nt are assumed already oriented for the side of the surface being crossed
+fn refract(d : Vec3, n : Vec3, ni_over_nt : f64, cos_theta : f64) -> Vec3 {
+    let sin_theta_sq = 1.0 - cos_theta * cos_theta;
+    (d + n * cos_theta) * ni_over_nt - n * (1.0 - ni_over_nt * ni_over_nt * sin_theta_sq).sqrt()
+}
+
+// Schlick's approximation of the Fresnel reflectance for a dielectric surface
+fn schlick(cos_theta : f64, ior : f64) -> f64 {
+    let r0 = ((1.0 - ior) / (1.0 + ior)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos_theta).powi(5)
+}
+
+// Scatters an incoming ray off a hit surface, returning the attenuation and the
+// scattered ray, or None if the ray is absorbed.
+fn scatter(ray : &Ray, hit : &HitRecord) -> Option<(Vec3, Ray)> {
+    match hit.mat {
+        Material::Lambertian { albedo } => {
+            let mut scatter_dir = hit.norm + random_unit_vector();
+            if near_zero(scatter_dir) {
+                scatter_dir = hit.norm;
             }
-            return vec3(1.0, 0.0, 0.0);
+            Some((albedo, Ray::new(hit.point, normalize(scatter_dir), ray.time)))
         }
 
-        if let Some(_hit2) = sphere2_hit {
-            return vec3(0.0, 1.0, 0.0);
+        Material::Metal { albedo, fuzz } => {
+            let reflected = reflect(normalize(ray.dir), hit.norm) + random_in_unit_sphere() * fuzz;
+            if dot(reflected, hit.norm) > 0.0 {
+                Some((albedo, Ray::new(hit.point, normalize(reflected), ray.time)))
+            } else {
+                None
+            }
         }
 
-        vec3(1.0, 0.0, 1.0)
+        Material::Dielectric { ior } => {
+            let unit_dir = normalize(ray.dir);
+
+            let (n, ni_over_nt) = if dot(unit_dir, hit.norm) > 0.0 {
+                (-hit.norm, ior)
+            } else {
+                (hit.norm, 1.0 / ior)
+            };
+
+            let cos_theta = dot(-unit_dir, n).min(1.0);
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+            let direction = if ni_over_nt * sin_theta > 1.0 || random_f64() < schlick(cos_theta, ior) {
+                reflect(unit_dir, n)
+            } else {
+                refract(unit_dir, n, ni_over_nt, cos_theta)
+            };
+
+            Some((vec3(1.0, 1.0, 1.0), Ray::new(hit.point, normalize(direction), ray.time)))
+        }
     }
 }
 
+// Walks a shadow ray from `from` toward `to`, multiplying visibility by the
+// transmission_coefficient of every surface it crosses along the way. A fully opaque
+// surface zeroes visibility out (a hard shadow); glass only dims it (a tinted one).
+fn shadow_visibility(scene : &dyn Hittable, from : Vec3, to : Vec3, time : f64) -> Vec3 {
+    const EPS : f64 = 1e-4;
 
-struct RayIterator {
-    i : usize,
+    let mut origin = from;
+    let mut visibility = vec3(1.0, 1.0, 1.0);
+
+    loop {
+        let to_light = to - origin;
+        let remaining = dot(to_light, to_light).sqrt();
+
+        if remaining < EPS || near_zero(visibility) {
+            break;
+        }
+
+        let dir = to_light / remaining;
+        let shadow_ray = Ray::new(origin, dir, time);
+
+        match scene.hit(&shadow_ray, EPS, remaining - EPS) {
+            Some(hit) => {
+                visibility = mul(visibility, hit.mat.transmission_coefficient());
+                origin = hit.point + dir * EPS;
+            }
+            None => break,
+        }
+    }
+
+    visibility
+}
+
+// Direct lighting via shadow rays, added on top of the recursive indirect term.
+// Only diffuse surfaces receive it directly; specular/refractive materials get their
+// appearance entirely from the recursive bounce in `Ray::cast`.
+fn direct_lighting(scene : &dyn Hittable, hit : &HitRecord, time : f64, lights : &[PointLight]) -> Vec3 {
+    let albedo = match hit.mat {
+        Material::Lambertian { albedo } => albedo,
+        _ => return vec3(0.0, 0.0, 0.0),
+    };
+
+    let mut total = vec3(0.0, 0.0, 0.0);
+
+    for light in lights {
+        let to_light = light.position - hit.point;
+        let dist_sq = dot(to_light, to_light);
+        if dist_sq < 1e-8 {
+            continue;
+        }
+
+        let light_dir = to_light / dist_sq.sqrt();
+        let n_dot_l = dot(hit.norm, light_dir).max(0.0);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+
+        let visibility = shadow_visibility(scene, hit.point + hit.norm * 1e-4, light.position, time);
+        if near_zero(visibility) {
+            continue;
+        }
+
+        let falloff = light.intensity / dist_sq.max(1e-4);
+        total = total + mul(mul(albedo, light.color), visibility) * (n_dot_l * falloff);
+    }
+
+    total
+}
+
+
+impl Ray {
+    fn new(origin : Vec3, dir : Vec3, time : f64) -> Self {
+        Self {origin, dir, time}
+    }
+
+    fn cast(&self, scene : &dyn Hittable, lights : &[PointLight], depth : u32) -> Vec3 {
+        if depth == 0 {
+            return vec3(0.0, 0.0, 0.0);
+        }
+
+        let Some(hit) = scene.hit(self, 0.001, 10_000.0) else {
+            return vec3(1.0, 0.0, 1.0);
+        };
 
+        let direct = direct_lighting(scene, &hit, self.time, lights);
+
+        match scatter(self, &hit) {
+            Some((attenuation, scattered)) => direct + mul(attenuation, scattered.cast(scene, lights, depth - 1)),
+            None => direct,
+        }
+    }
+}
+
+
+struct RayIterator {
     samples_per_pixel : usize,
-    total_samples : usize,
 
     sensor_bottom_left: Vec3,
     sensor_x_axis : Vec3,
@@ -102,6 +323,12 @@ struct RayIterator {
     sensor : Vec2,
 
     focal_point : Vec3,
+
+    lens_radius : f64,
+    focus_dist : f64,
+
+    shutter_open : f64,
+    shutter_close : f64,
 }
 
 
@@ -122,51 +349,71 @@ impl RayIterator {
 
         // This is kind of arbitrary, but it will work for now
         let samples_per_pixel : usize = (100.0 * cam.exposure) as usize;
-        
-        // TODO: don't use the window width and height directly like this?
-        let total_samples = samples_per_pixel * WINDOW_WIDTH * WINDOW_HEIGHT;
 
         let sensor = cam.sensor;
 
         Self {
-            i: 0, 
-            samples_per_pixel, 
-            total_samples,
+            samples_per_pixel,
             sensor_bottom_left,
             sensor_x_axis, 
             sensor_y_axis,
             sensor,
             focal_point,
+            lens_radius: cam.lens_radius,
+            focus_dist: cam.focus_dist,
+            shutter_open: cam.shutter_open,
+            shutter_close: cam.shutter_close,
         }
     }
 }
 
 
-impl Iterator for RayIterator {
-    type Item = (IVec2, Ray);
+impl RayIterator {
+    // Generate a single sample ray aimed at the given pixel, so render threads can ask
+    // for a ray for whichever pixel of their tile they are working on.
+    fn ray_for_pixel(&self, pixel : IVec2) -> Ray {
+        // How far to move along the sensor from the bottom left, (0, 0) = bottom left, (1, 1) = top right.
+        // Jitter within the pixel's footprint so repeated samples land at different sub-pixel
+        // offsets instead of stacking the exact same ray, which is what actually buys us anti-aliasing.
+        let advance = vec2(
+            pixel.x as f64 / WINDOW_WIDTH as f64 + random_f64() / WINDOW_WIDTH as f64,
+            pixel.y as f64 / WINDOW_HEIGHT as f64 + random_f64() / WINDOW_HEIGHT as f64,
+        );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let i = self.i;
-        self.i += 1;
+        // Finally, the actual point on the sensor we are looking for!
+        let sensor_point = self.sensor_bottom_left + self.sensor_x_axis * advance.x * self.sensor.x + self.sensor_y_axis * advance.y * self.sensor.y;
 
-        if i >= self.total_samples {
-            return None
-        }
+        // Where this pixel's subject actually sits: walk the un-jittered pinhole
+        // direction out to focus_dist, rather than reusing focal_point (which is just
+        // the fixed point that sets the field of view, not where the scene is).
+        let pinhole_dir = normalize(self.focal_point - sensor_point);
+        let focus_point = sensor_point + pinhole_dir * self.focus_dist;
 
-        let pixel_index = i / self.samples_per_pixel;
-        let pixel = ivec2((pixel_index % WINDOW_WIDTH) as i32, (pixel_index / WINDOW_WIDTH) as i32); 
+        // Simulate a thin lens: jitter the ray's origin across the lens disk, but keep it
+        // aimed at the same in-focus point, so only things off the focal plane blur out
+        let lens_offset = random_in_unit_disk() * self.lens_radius;
+        let ray_origin = sensor_point + self.sensor_x_axis * lens_offset.x + self.sensor_y_axis * lens_offset.y;
+        let ray_dir = normalize(focus_point - ray_origin);
 
-        // How far to move along the sensor from the bottom left, (0, 0) = bottom left, (1, 1) = top right
-        let advance = vec2(pixel.x as f64 / WINDOW_WIDTH as f64, pixel.y as f64 / WINDOW_HEIGHT as f64);
-        
-        // Finally, the actual point on the sensor we are looking for!
-        let ray_origin = self.sensor_bottom_left + self.sensor_x_axis * advance.x * self.sensor.x + self.sensor_y_axis * advance.y * self.sensor.y;
-        
-        // And of course, our ray direction is just toward the focal point!
-        // Maybe adding randomness here to simulate an imperfect lens would be fun?
-        let ray_dir = normalize(self.focal_point - ray_origin);
+        // Give the ray a moment within the shutter interval; moving spheres sample their
+        // position at this time, which is what actually produces the motion-blur smear
+        let time = random_f64_range(self.shutter_open, self.shutter_close);
+
+        Ray::new(ray_origin, ray_dir, time)
+    }
 
-        Some((pixel, Ray::new(ray_origin, ray_dir)))
+    // The camera basis the GPU compute shader needs to generate its own primary rays;
+    // narrows everything down to f32 since that's what the shader's uniforms take.
+    fn gpu_params(&self) -> GpuCameraParams {
+        let as_f32 = |v : Vec3| (v.x as f32, v.y as f32, v.z as f32);
+
+        GpuCameraParams {
+            sensor_bottom_left: as_f32(self.sensor_bottom_left),
+            sensor_x_axis: as_f32(self.sensor_x_axis),
+            sensor_y_axis: as_f32(self.sensor_y_axis),
+            sensor_size: (self.sensor.x as f32, self.sensor.y as f32),
+            focal_point: as_f32(self.focal_point),
+        }
     }
 }
 
@@ -182,18 +429,29 @@ struct Camera {
     exposure : f64,     // Amount of "time" to expose for, higher values generate more rays
     focal_length : f64, // The distance from sensor to where the light crosses over
     iso : f64,          // How much color to add to the image for each ray
+    aperture : f64,     // Diameter of the lens, controls the strength of depth-of-field blur
+    lens_radius : f64,  // aperture / 2, kept around so we don't recompute it per-ray
+    focus_dist : f64,   // Distance from the camera to the plane that renders in sharp focus
+
+    shutter_open : f64,  // Time the shutter opens, start of the exposure window
+    shutter_close : f64, // Time the shutter closes; moving objects sweep between these two times
 }
 
 
 impl Camera {
-    fn new(position : Vec3, look : Vec3, sensor : Vec2, exposure : f64, focal_length : f64, iso : f64) -> Self {
+    fn new(position : Vec3, look : Vec3, sensor : Vec2, exposure : f64, focal_length : f64, iso : f64, aperture : f64, focus_dist : f64) -> Self {
         Self {
             position,
             look,
             sensor,
             exposure,
             focal_length,
-            iso
+            iso,
+            aperture,
+            lens_radius: aperture / 2.0,
+            focus_dist,
+            shutter_open: 0.0,
+            shutter_close: exposure,
         }
     }
 
@@ -205,7 +463,9 @@ impl Camera {
 
 
 fn ray_sphere_intersection(ray: &Ray, sphere: &Sphere, t_min : f64, t_max : f64) -> Option<HitRecord> {
-    let oc = ray.origin - sphere.center;
+    let center = sphere.center_at(ray.time);
+
+    let oc = ray.origin - center;
     let a = glm::dot(ray.dir, ray.dir);
     let half_b = glm::dot(oc, ray.dir);
     let c = glm::dot(oc, oc) - sphere.radius * sphere.radius;
@@ -229,9 +489,9 @@ fn ray_sphere_intersection(ray: &Ray, sphere: &Sphere, t_min : f64, t_max : f64)
 
     let dist = root;
     let point = ray.origin + ray.dir * dist;
-    let norm = (point - sphere.center) / sphere.radius;
-    return Some(HitRecord{dist, norm, point});
-    
+    let norm = (point - center) / sphere.radius;
+    return Some(HitRecord{dist, norm, point, mat: sphere.material});
+
 }
 
 
@@ -240,9 +500,110 @@ fn to_color(x: Vec3) -> Color {
     color((x.x * 255.0) as u8, (x.y * 255.0) as u8, (x.z * 255.0) as u8)
 }
 
+// Gamma-correct a linear radiance value for display (gamma = 2.0, i.e. sqrt)
+fn gamma_correct(x: Vec3) -> Vec3 {
+    vec3(x.x.sqrt(), x.y.sqrt(), x.z.sqrt())
+}
+
+
+// A rectangular block of pixels handed out as one unit of work to a render thread
+#[derive(Copy, Clone)]
+struct Tile {
+    x0 : usize,
+    y0 : usize,
+    x1 : usize,
+    y1 : usize,
+}
+
+// Split the frame into fixed-size blocks, left-to-right, starting from the bottom row (y = 0).
+// The last tile in each row/column is clipped to the window edge.
+fn build_tiles() -> VecDeque<Tile> {
+    let mut tiles = VecDeque::new();
+
+    let mut y = 0;
+    while y < WINDOW_HEIGHT {
+        let mut x = 0;
+        while x < WINDOW_WIDTH {
+            tiles.push_back(Tile {
+                x0: x,
+                y0: y,
+                x1: (x + BLOCK_SIZE).min(WINDOW_WIDTH),
+                y1: (y + BLOCK_SIZE).min(WINDOW_HEIGHT),
+            });
+            x += BLOCK_SIZE;
+        }
+        y += BLOCK_SIZE;
+    }
+
+    tiles
+}
+
+// Render one frame by handing tiles out to a pool of worker threads. Workers own disjoint
+// pixel ranges and write into their own local buffer, so the only shared state that needs
+// locking is the tile queue itself; the finished tiles are merged into the renderer
+// serially as they come back over the channel.
+fn render_frame(cam : &Camera, renderer : &mut Renderer, scene : &dyn Hittable, lights : &[PointLight]) {
+    let rays = cam.rays();
+    let samples_per_pixel = rays.samples_per_pixel;
+
+    let tile_queue = Mutex::new(build_tiles());
+    let thread_count = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    let (result_tx, result_rx) = mpsc::channel::<(Tile, Vec<Color>)>();
+
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            let tile_queue = &tile_queue;
+            let rays = &rays;
+            let result_tx = result_tx.clone();
+
+            scope.spawn(move || {
+                while let Some(tile) = tile_queue.lock().unwrap().pop_front() {
+                    let mut pixels = Vec::with_capacity((tile.x1 - tile.x0) * (tile.y1 - tile.y0));
+
+                    for y in tile.y0..tile.y1 {
+                        for x in tile.x0..tile.x1 {
+                            let pixel = ivec2(x as i32, y as i32);
+
+                            // Accumulate in f64 precision, then average and gamma-correct once per
+                            // pixel instead of letting the last sample clobber all the others
+                            let mut accumulated = vec3(0.0, 0.0, 0.0);
+                            for _ in 0..samples_per_pixel {
+                                accumulated = accumulated + rays.ray_for_pixel(pixel).cast(scene, lights, MAX_DEPTH);
+                            }
+                            let averaged = accumulated / samples_per_pixel as f64;
+
+                            pixels.push(to_color(gamma_correct(averaged)));
+                        }
+                    }
+
+                    result_tx.send((tile, pixels)).unwrap();
+                }
+            });
+        }
+
+        drop(result_tx);
+
+        for (tile, pixels) in result_rx {
+            let mut i = 0;
+            for y in tile.y0..tile.y1 {
+                for x in tile.x0..tile.x1 {
+                    renderer.set_pixel(x as u32, y as u32, &pixels[i]);
+                    i += 1;
+                }
+            }
+        }
+    });
+}
+
+
+// Which backend the demo in `main` renders with. The GPU backend only knows the
+// original two-sphere material demo (see the comment on COMPUTE_SHADER_SRC), so
+// switching this to Gpu drops the mesh floor, motion blur and shadow rays below.
+const RENDER_MODE : renderer::RenderMode = renderer::RenderMode::Cpu;
 
 fn main() {
-    let mut renderer = Renderer::create(WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
+    let mut renderer = Renderer::create(WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32, RENDER_MODE);
     renderer.initialize();
 
     let cam_pos = vec3(10.0, 10.0, 10.0);
@@ -250,21 +611,41 @@ fn main() {
 
     let sensor_size = vec2(millimeters(36.0), millimeters(24.0));
 
-    let mut cam = Camera::new(cam_pos, cam_look, sensor_size, 0.01, millimeters(50.0), 1.0);
-
+    // The spheres sit at the origin, so that's what should be in sharp focus
+    let focus_dist = dot(cam_pos, cam_pos).sqrt();
+
+    let mut cam = Camera::new(cam_pos, cam_look, sensor_size, 0.01, millimeters(50.0), 1.0, millimeters(2.0), focus_dist);
+
+    // The geometry itself doesn't change frame to frame, so build the BVH once up front
+    // rather than re-fitting boxes around the same two spheres on every ray.
+    let mut objects : Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {center : vec3(0.0, 0.0, 0.0), radius: 2.0, material : Material::Dielectric { ior: 1.5 }, motion: None}),
+        Box::new(Sphere {
+            center: vec3(0.0, 3.0, 0.0),
+            radius: 1.0,
+            material: Material::Metal { albedo: vec3(0.8, 0.8, 0.9), fuzz: 0.1 },
+            motion: Some(Motion { center1: vec3(1.0, 3.0, 0.0), time0: cam.shutter_open, time1: cam.shutter_close }),
+        }),
+    ];
+    objects.extend(scene::load_obj("assets/floor.obj"));
+    let scene = scene::Bvh::build(objects);
+
+    let lights = vec![
+        PointLight { position: vec3(8.0, 8.0, 8.0), color: vec3(1.0, 1.0, 1.0), intensity: 80.0 },
+    ];
 
     let mut t : f64 = 0.0;
 
     while !renderer.should_close() {
         t += 0.1;
         cam.focal_length += millimeters(t.sin() * 5.0);
-        for (pixel, ray) in cam.rays() {
-            //println!("{:?}: {:?} -> {:?}", pixel, ray, ray.cast());
-            renderer.set_pixel(pixel.x as u32, pixel.y as u32,  &to_color(ray.cast()));
+
+        match RENDER_MODE {
+            renderer::RenderMode::Cpu => render_frame(&cam, &mut renderer, &scene, &lights),
+            renderer::RenderMode::Gpu => renderer.render_gpu(&cam.rays().gpu_params()),
         }
-    
 
         renderer.update();
-        
+
     }
 }
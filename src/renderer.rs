@@ -20,6 +20,12 @@ pub fn color(r: u8, g: u8, b: u8) -> Color {
     Color{r:r, g:g, b:b}
 }
 
+// `name` must be nul-terminated, since GetUniformLocation wants a C string
+unsafe fn set_uniform_vec3(program : u32, name : &str, v : (f32, f32, f32)) {
+    let loc = gl::GetUniformLocation(program, name.as_ptr() as *const i8);
+    gl::Uniform3f(loc, v.0, v.1, v.2);
+}
+
 
 const VERT_SHADER_SRC : &str = r#"
 #version 330 core
@@ -49,6 +55,225 @@ void main()
 }
 "#;
 
+
+// Ports Ray::cast's two-sphere material demo onto the GPU: ray generation and
+// sphere/material intersection run per-pixel in the compute shader instead of on the
+// CPU, writing straight into the RGBA32F image the blit shader already samples from.
+//
+// This mirrors the original chunk0-1 demo scene only: it has no depth of field, no
+// triangles/BVH, no motion blur, and no shadow rays, so scenes built with the later
+// mesh-loading/motion-blur/direct-lighting features will render differently (or not
+// at all) here than they do through the CPU path. Keeping it in sync with those is
+// future work, not something this backend does today.
+const COMPUTE_SHADER_SRC : &str = r#"
+#version 430 core
+layout (local_size_x = 16, local_size_y = 16) in;
+layout (rgba32f, binding = 0) uniform image2D destImage;
+
+uniform vec3 sensorBottomLeft;
+uniform vec3 sensorXAxis;
+uniform vec3 sensorYAxis;
+uniform vec2 sensorSize;
+uniform vec3 focalPoint;
+
+const int MAX_DEPTH = 8;
+
+struct Sphere {
+    vec3 center;
+    float radius;
+    int materialType; // 0 = lambertian, 1 = metal, 2 = dielectric
+    vec3 albedo;
+    float param;       // fuzz for metal, ior for dielectric
+};
+
+const int SPHERE_COUNT = 2;
+const Sphere spheres[SPHERE_COUNT] = Sphere[](
+    Sphere(vec3(0.0, 0.0, 0.0), 2.0, 2, vec3(1.0), 1.5),
+    Sphere(vec3(0.0, 3.0, 0.0), 1.0, 1, vec3(0.8, 0.8, 0.9), 0.1)
+);
+
+uint rngState;
+
+uint hashU32(uint x) {
+    x ^= x >> 16;
+    x *= 0x7feb352du;
+    x ^= x >> 15;
+    x *= 0x846ca68bu;
+    x ^= x >> 16;
+    return x;
+}
+
+float randomFloat() {
+    rngState = hashU32(rngState);
+    return float(rngState) / 4294967295.0;
+}
+
+vec3 randomInUnitSphere() {
+    for (int i = 0; i < 16; i++) {
+        vec3 p = vec3(randomFloat(), randomFloat(), randomFloat()) * 2.0 - 1.0;
+        if (dot(p, p) < 1.0) {
+            return p;
+        }
+    }
+    return vec3(0.0);
+}
+
+struct HitRecord {
+    float dist;
+    vec3 point;
+    vec3 norm;
+    int materialType;
+    vec3 albedo;
+    float param;
+};
+
+bool hitSphere(Sphere s, vec3 origin, vec3 dir, float tMin, float tMax, out HitRecord rec) {
+    vec3 oc = origin - s.center;
+    float a = dot(dir, dir);
+    float halfB = dot(oc, dir);
+    float c = dot(oc, oc) - s.radius * s.radius;
+    float disc = halfB * halfB - a * c;
+
+    if (disc < 0.0) {
+        return false;
+    }
+
+    float sqrtd = sqrt(disc);
+    float root = (-halfB - sqrtd) / a;
+    if (root < tMin || root > tMax) {
+        root = (-halfB + sqrtd) / a;
+        if (root < tMin || root > tMax) {
+            return false;
+        }
+    }
+
+    rec.dist = root;
+    rec.point = origin + dir * root;
+    rec.norm = (rec.point - s.center) / s.radius;
+    rec.materialType = s.materialType;
+    rec.albedo = s.albedo;
+    rec.param = s.param;
+    return true;
+}
+
+bool hitScene(vec3 origin, vec3 dir, out HitRecord rec) {
+    bool hitAnything = false;
+    float closest = 10000.0;
+
+    for (int i = 0; i < SPHERE_COUNT; i++) {
+        HitRecord tmp;
+        if (hitSphere(spheres[i], origin, dir, 0.001, closest, tmp)) {
+            hitAnything = true;
+            closest = tmp.dist;
+            rec = tmp;
+        }
+    }
+
+    return hitAnything;
+}
+
+float schlick(float cosTheta, float ior) {
+    float r0 = (1.0 - ior) / (1.0 + ior);
+    r0 *= r0;
+    return r0 + (1.0 - r0) * pow(1.0 - cosTheta, 5.0);
+}
+
+vec3 traceRay(vec3 origin, vec3 dir) {
+    vec3 attenuation = vec3(1.0);
+
+    for (int depth = 0; depth < MAX_DEPTH; depth++) {
+        HitRecord hit;
+        if (!hitScene(origin, dir, hit)) {
+            return attenuation * vec3(1.0, 0.0, 1.0); // background, matches the CPU path's magenta
+        }
+
+        if (hit.materialType == 0) {
+            // Lambertian
+            vec3 scatterDir = hit.norm + normalize(randomInUnitSphere());
+            if (dot(scatterDir, scatterDir) < 1e-8) {
+                scatterDir = hit.norm;
+            }
+            attenuation *= hit.albedo;
+            origin = hit.point;
+            dir = normalize(scatterDir);
+
+        } else if (hit.materialType == 1) {
+            // Metal
+            vec3 reflected = reflect(normalize(dir), hit.norm) + randomInUnitSphere() * hit.param;
+            if (dot(reflected, hit.norm) <= 0.0) {
+                return vec3(0.0);
+            }
+            attenuation *= hit.albedo;
+            origin = hit.point;
+            dir = normalize(reflected);
+
+        } else {
+            // Dielectric
+            vec3 unitDir = normalize(dir);
+            vec3 n = hit.norm;
+            float niOverNt = 1.0 / hit.param;
+
+            if (dot(unitDir, hit.norm) > 0.0) {
+                n = -hit.norm;
+                niOverNt = hit.param;
+            }
+
+            float cosTheta = min(dot(-unitDir, n), 1.0);
+            float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+
+            vec3 newDir;
+            if (niOverNt * sinTheta > 1.0 || randomFloat() < schlick(cosTheta, hit.param)) {
+                newDir = reflect(unitDir, n);
+            } else {
+                float sinThetaSq = 1.0 - cosTheta * cosTheta;
+                newDir = (unitDir + n * cosTheta) * niOverNt - n * sqrt(1.0 - niOverNt * niOverNt * sinThetaSq);
+            }
+
+            origin = hit.point;
+            dir = normalize(newDir);
+        }
+    }
+
+    return vec3(0.0); // ran out of bounces
+}
+
+void main() {
+    ivec2 pixel = ivec2(gl_GlobalInvocationID.xy);
+    ivec2 imgSize = imageSize(destImage);
+
+    if (pixel.x >= imgSize.x || pixel.y >= imgSize.y) {
+        return;
+    }
+
+    rngState = uint(pixel.x) * 1973u + uint(pixel.y) * 9277u + 1u;
+
+    vec2 advance = vec2(float(pixel.x) / float(imgSize.x), float(pixel.y) / float(imgSize.y));
+    vec3 rayOrigin = sensorBottomLeft + sensorXAxis * advance.x * sensorSize.x + sensorYAxis * advance.y * sensorSize.y;
+    vec3 rayDir = normalize(focalPoint - rayOrigin);
+
+    imageStore(destImage, pixel, vec4(traceRay(rayOrigin, rayDir), 1.0));
+}
+"#;
+
+
+// Which hardware path generates the image: the original per-pixel CPU loop, or the
+// compute-shader backend that writes straight into the display texture on the GPU.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum RenderMode {
+    Cpu,
+    Gpu,
+}
+
+// Camera basis the GPU path needs to generate primary rays; mirrors the fields
+// RayIterator precomputes for the CPU path.
+pub struct GpuCameraParams {
+    pub sensor_bottom_left : (f32, f32, f32),
+    pub sensor_x_axis : (f32, f32, f32),
+    pub sensor_y_axis : (f32, f32, f32),
+    pub sensor_size : (f32, f32),
+    pub focal_point : (f32, f32, f32),
+}
+
 pub struct Renderer {
     glfw : Option<Glfw>,
     window : Option<glfw::Window>,
@@ -60,10 +285,12 @@ pub struct Renderer {
     vbo: u32,
     texture: u32,
     program: u32,
+    mode: RenderMode,
+    compute_program: u32,
 }
 
 impl Renderer {
-    pub fn create(width : u32, height : u32) -> Self {
+    pub fn create(width : u32, height : u32, mode : RenderMode) -> Self {
         Self {
             glfw: None,
             window: None,
@@ -74,7 +301,9 @@ impl Renderer {
             vao: 0,
             vbo: 0,
             texture: 0,
-            program: 0
+            program: 0,
+            mode,
+            compute_program: 0,
         }
     }
 
@@ -112,8 +341,17 @@ impl Renderer {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
     
-            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, self.width as i32, self.height as i32, 0, gl::RGB, gl::UNSIGNED_BYTE, self.image_data.as_mut_ptr() as *const c_void);
-    
+            match self.mode {
+                RenderMode::Cpu => {
+                    gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, self.width as i32, self.height as i32, 0, gl::RGB, gl::UNSIGNED_BYTE, self.image_data.as_mut_ptr() as *const c_void);
+                }
+                RenderMode::Gpu => {
+                    // The compute shader writes straight into this image via glBindImageTexture,
+                    // so it needs to be float and there's no CPU-side data to seed it with.
+                    gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA32F as i32, self.width as i32, self.height as i32, 0, gl::RGBA, gl::FLOAT, std::ptr::null());
+                }
+            }
+
             // Setup a VAO
             gl::GenVertexArrays(1, &mut self.vao);
             gl::BindVertexArray(self.vao);
@@ -153,9 +391,43 @@ impl Renderer {
             gl::AttachShader(self.program, frag_shader_id);
     
             gl::LinkProgram(self.program);
+
+            if self.mode == RenderMode::Gpu {
+                let compute_shader_id = gl::CreateShader(gl::COMPUTE_SHADER);
+
+                let compute_shader_src_ptr = COMPUTE_SHADER_SRC.as_ptr() as *const i8;
+                let compute_shader_len = COMPUTE_SHADER_SRC.len() as i32;
+
+                gl::ShaderSource(compute_shader_id, 1, &compute_shader_src_ptr as *const *const i8, &compute_shader_len);
+                gl::CompileShader(compute_shader_id);
+
+                self.compute_program = gl::CreateProgram();
+                gl::AttachShader(self.compute_program, compute_shader_id);
+                gl::LinkProgram(self.compute_program);
+            }
+        }
+    }
+
+    // Dispatch the compute-shader ray tracer for one frame. Only valid when this
+    // Renderer was created in RenderMode::Gpu.
+    pub fn render_gpu(&mut self, params : &GpuCameraParams) {
+        unsafe {
+            gl::UseProgram(self.compute_program);
+
+            set_uniform_vec3(self.compute_program, "sensorBottomLeft\0", params.sensor_bottom_left);
+            set_uniform_vec3(self.compute_program, "sensorXAxis\0", params.sensor_x_axis);
+            set_uniform_vec3(self.compute_program, "sensorYAxis\0", params.sensor_y_axis);
+            set_uniform_vec3(self.compute_program, "focalPoint\0", params.focal_point);
+
+            let sensor_size_loc = gl::GetUniformLocation(self.compute_program, "sensorSize\0".as_ptr() as *const i8);
+            gl::Uniform2f(sensor_size_loc, params.sensor_size.0, params.sensor_size.1);
+
+            gl::BindImageTexture(0, self.texture, 0, gl::FALSE, 0, gl::WRITE_ONLY, gl::RGBA32F);
+            gl::DispatchCompute((self.width + 15) / 16, (self.height + 15) / 16, 1);
+            gl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT | gl::TEXTURE_FETCH_BARRIER_BIT);
         }
     }
- 
+
     pub fn update(&mut self) {
         let glfw = self.glfw.as_mut().unwrap();
         let events = self.events.as_ref().unwrap();
@@ -171,9 +443,13 @@ impl Renderer {
             gl::UseProgram(self.program);
             gl::BindTexture(gl::TEXTURE_2D, self.texture);
 
-            // Upload texture to GPU
-            // TODO: Track if this really changed, save time by only updating when necessary!
-            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, self.width as i32, self.height as i32, 0, gl::RGB, gl::UNSIGNED_BYTE, self.image_data.as_mut_ptr() as *const c_void);
+            if self.mode == RenderMode::Cpu {
+                // Upload texture to GPU
+                // TODO: Track if this really changed, save time by only updating when necessary!
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, self.width as i32, self.height as i32, 0, gl::RGB, gl::UNSIGNED_BYTE, self.image_data.as_mut_ptr() as *const c_void);
+            }
+            // In RenderMode::Gpu the compute shader already wrote this frame's pixels
+            // straight into the texture via render_gpu, so there's nothing to re-upload.
 
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
             gl::DrawArrays(gl::TRIANGLES, 0, 6);
@@ -0,0 +1,285 @@
+// Geometry abstraction layer: a `Hittable` trait lets `Ray::cast` query arbitrary
+// primitives (spheres, triangles) through one interface, and a `Bvh` built over them
+// keeps per-ray cost from growing linearly with scene complexity.
+
+extern crate glm;
+extern crate tobj;
+
+use glm::{cross, dot, normalize};
+
+use crate::{vec3, HitRecord, Material, Ray, Sphere, Vec3};
+
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min : Vec3,
+    pub max : Vec3,
+}
+
+impl Aabb {
+    pub fn surrounding(a : Aabb, b : Aabb) -> Aabb {
+        Aabb {
+            min: vec3(a.min.x.min(b.min.x), a.min.y.min(b.min.y), a.min.z.min(b.min.z)),
+            max: vec3(a.max.x.max(b.max.x), a.max.y.max(b.max.y), a.max.z.max(b.max.z)),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    // Slab test: walk each axis narrowing [t_min, t_max] to the interval the ray is
+    // inside the box; if that interval ever goes empty, the ray missed.
+    pub fn hit(&self, ray : &Ray, t_min : f64, t_max : f64) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+
+        for axis in 0..3 {
+            let (origin, dir, lo, hi) = match axis {
+                0 => (ray.origin.x, ray.dir.x, self.min.x, self.max.x),
+                1 => (ray.origin.y, ray.dir.y, self.min.y, self.max.y),
+                _ => (ray.origin.z, ray.dir.z, self.min.z, self.max.z),
+            };
+
+            let inv_dir = 1.0 / dir;
+            let mut t0 = (lo - origin) * inv_dir;
+            let mut t1 = (hi - origin) * inv_dir;
+
+            if inv_dir < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+pub trait Hittable : Sync {
+    fn hit(&self, ray : &Ray, t_min : f64, t_max : f64) -> Option<HitRecord>;
+    fn bounding_box(&self) -> Aabb;
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray : &Ray, t_min : f64, t_max : f64) -> Option<HitRecord> {
+        crate::ray_sphere_intersection(ray, self, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = vec3(self.radius, self.radius, self.radius);
+        let box_at = |center : Vec3| Aabb { min: center - r, max: center + r };
+
+        match self.motion {
+            // The BVH only ever tests this static box, so a moving sphere needs one
+            // that covers its whole sweep or the time-aware hit test below can get
+            // culled before it ever runs.
+            Some(crate::Motion { center1, .. }) => {
+                Aabb::surrounding(box_at(self.center), box_at(center1))
+            }
+            None => box_at(self.center),
+        }
+    }
+}
+
+pub struct Triangle {
+    pub v0 : Vec3,
+    pub v1 : Vec3,
+    pub v2 : Vec3,
+    pub material : Material,
+}
+
+impl Hittable for Triangle {
+    // Moller-Trumbore ray/triangle intersection
+    fn hit(&self, ray : &Ray, t_min : f64, t_max : f64) -> Option<HitRecord> {
+        const EPSILON : f64 = 1e-8;
+
+        let edge1 = self.v1 - self.v0;
+        let edge2 = self.v2 - self.v0;
+
+        let pvec = cross(ray.dir, edge2);
+        let det = dot(edge1, pvec);
+
+        if det.abs() < EPSILON {
+            return None; // ray is parallel to the triangle's plane
+        }
+
+        let inv_det = 1.0 / det;
+        let tvec = ray.origin - self.v0;
+
+        let u = dot(tvec, pvec) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let qvec = cross(tvec, edge1);
+        let v = dot(ray.dir, qvec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let dist = dot(edge2, qvec) * inv_det;
+        if dist < t_min || dist > t_max {
+            return None;
+        }
+
+        let point = ray.origin + ray.dir * dist;
+
+        let mut norm = normalize(cross(edge1, edge2));
+        if dot(norm, ray.dir) > 0.0 {
+            norm = -norm;
+        }
+
+        Some(HitRecord { dist, norm, point, mat: self.material })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb {
+            min: vec3(
+                self.v0.x.min(self.v1.x).min(self.v2.x),
+                self.v0.y.min(self.v1.y).min(self.v2.y),
+                self.v0.z.min(self.v1.z).min(self.v2.z),
+            ),
+            max: vec3(
+                self.v0.x.max(self.v1.x).max(self.v2.x),
+                self.v0.y.max(self.v1.y).max(self.v2.y),
+                self.v0.z.max(self.v1.z).max(self.v2.z),
+            ),
+        }
+    }
+}
+
+// A bounding-volume hierarchy over a set of Hittables. Built by recursively splitting
+// along the longest axis of the containing box at the median, which keeps each split
+// to an O(n log n) sort rather than anything fancier.
+pub enum Bvh {
+    Leaf(Box<dyn Hittable>),
+    Node { bbox : Aabb, left : Box<Bvh>, right : Box<Bvh> },
+}
+
+impl Bvh {
+    pub fn build(mut objects : Vec<Box<dyn Hittable>>) -> Bvh {
+        assert!(!objects.is_empty(), "cannot build a BVH over an empty object list");
+
+        if objects.len() == 1 {
+            return Bvh::Leaf(objects.pop().unwrap());
+        }
+
+        let bbox = objects
+            .iter()
+            .map(|o| o.bounding_box())
+            .reduce(Aabb::surrounding)
+            .unwrap();
+
+        let extent = bbox.max - bbox.min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+
+        objects.sort_by(|a, b| {
+            let ca = a.bounding_box().centroid();
+            let cb = b.bounding_box().centroid();
+
+            let (va, vb) = match axis {
+                0 => (ca.x, cb.x),
+                1 => (ca.y, cb.y),
+                _ => (ca.z, cb.z),
+            };
+
+            va.partial_cmp(&vb).unwrap()
+        });
+
+        let right_objects = objects.split_off(objects.len() / 2);
+
+        Bvh::Node {
+            bbox,
+            left: Box::new(Bvh::build(objects)),
+            right: Box::new(Bvh::build(right_objects)),
+        }
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray : &Ray, t_min : f64, t_max : f64) -> Option<HitRecord> {
+        match self {
+            Bvh::Leaf(obj) => obj.hit(ray, t_min, t_max),
+
+            Bvh::Node { bbox, left, right } => {
+                if !bbox.hit(ray, t_min, t_max) {
+                    return None;
+                }
+
+                let hit_left = left.hit(ray, t_min, t_max);
+                let closer = hit_left.as_ref().map_or(t_max, |h| h.dist);
+                let hit_right = right.hit(ray, t_min, closer);
+
+                hit_right.or(hit_left)
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self {
+            Bvh::Leaf(obj) => obj.bounding_box(),
+            Bvh::Node { bbox, .. } => *bbox,
+        }
+    }
+}
+
+// Load every triangle out of an .obj (plus its companion .mtl) as Hittables, ready to
+// be handed to Bvh::build. Scenes like the Cornell box ship as a handful of meshes
+// rather than hard-coded primitives, so this is how those get into the renderer.
+pub fn load_obj(path : &str) -> Vec<Box<dyn Hittable>> {
+    let (models, materials) = tobj::load_obj(
+        path,
+        &tobj::LoadOptions { triangulate: true, single_index: true, ..Default::default() },
+    ).expect("failed to load obj file");
+
+    let materials = materials.expect("failed to load mtl file");
+
+    let mut triangles : Vec<Box<dyn Hittable>> = Vec::new();
+
+    for model in models {
+        let mesh = &model.mesh;
+
+        let material = mesh.material_id
+            .map(|id| material_from_mtl(&materials[id]))
+            .unwrap_or(Material::Lambertian { albedo: vec3(0.8, 0.8, 0.8) });
+
+        let vertex = |i : u32| {
+            let idx = i as usize * 3;
+            vec3(mesh.positions[idx] as f64, mesh.positions[idx + 1] as f64, mesh.positions[idx + 2] as f64)
+        };
+
+        for face in mesh.indices.chunks(3) {
+            triangles.push(Box::new(Triangle {
+                v0: vertex(face[0]),
+                v1: vertex(face[1]),
+                v2: vertex(face[2]),
+                material,
+            }));
+        }
+    }
+
+    triangles
+}
+
+fn material_from_mtl(mtl : &tobj::Material) -> Material {
+    let albedo = vec3(mtl.diffuse[0] as f64, mtl.diffuse[1] as f64, mtl.diffuse[2] as f64);
+
+    if mtl.dissolve < 1.0 {
+        Material::Dielectric { ior: mtl.optical_density as f64 }
+    } else if mtl.shininess > 200.0 {
+        Material::Metal { albedo, fuzz: (1.0 - mtl.shininess / 1000.0).clamp(0.0, 1.0) }
+    } else {
+        Material::Lambertian { albedo }
+    }
+}